@@ -0,0 +1,65 @@
+//! Optional Fluent-based localization of grammar-error presentation.
+//!
+//! Operators can drop a `messages` directory of `.ftl` files (one per locale,
+//! named after the locale, e.g. `se.ftl`, `nb.ftl`) next to the bundle or point
+//! at a directory with `--messages-dir`. Each file may define, per `error_code`, a message with
+//! `.title` and `.description` attributes:
+//!
+//! ```ftl
+//! typo =
+//!     .title = Stavefeil
+//!     .description = Ordet «{ $form }» ser ut til å være feilstavet.
+//! ```
+//!
+//! When a message id matches an `error_code`, the localized strings override the
+//! ones the bundle emitted; otherwise the pipeline-provided text is kept.
+
+use std::path::Path;
+
+use poem::i18n::{I18NArgs, I18NResources, Locale};
+
+use crate::GramcheckErrResponse;
+
+/// Load every `*.ftl` file in `dir` into an [`I18NResources`], using each file
+/// stem as its locale identifier.
+pub fn load_resources(dir: &Path) -> anyhow::Result<I18NResources> {
+    let mut builder = I18NResources::builder();
+
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("ftl") {
+            continue;
+        }
+        let Some(locale) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let text = std::fs::read_to_string(&path)?;
+        tracing::info!("Loaded messages for locale {}", locale);
+        builder = builder.add_ftl(locale, text);
+    }
+
+    Ok(builder.build()?)
+}
+
+/// Override `err`'s `title`/`description` with localized text when the
+/// negotiated [`Locale`] defines a message whose id equals the `error_code`.
+///
+/// `form` and each suggestion are exposed to Fluent as interpolation arguments
+/// (`$form`, `$suggestions`, `$suggestion_count`).
+pub fn localize(locale: &Locale, err: &mut GramcheckErrResponse) {
+    let args = || {
+        I18NArgs::default()
+            .set("form", err.error_text.clone())
+            .set("suggestions", err.suggestions.join(", "))
+            .set("suggestion_count", err.suggestions.len() as i64)
+    };
+
+    if let Ok(title) = locale.text_with_args(format!("{}.title", err.error_code), args()) {
+        err.title = title;
+    }
+    if let Ok(description) =
+        locale.text_with_args(format!("{}.description", err.error_code), args())
+    {
+        err.description = description;
+    }
+}
@@ -0,0 +1,304 @@
+//! Language Server Protocol front-end.
+//!
+//! Speaks LSP over stdio and drives the exact same [`check_document`] pipeline
+//! as the HTTP server. Editors (VS Code, Helix, Neovim, …) can consume grammar
+//! diagnostics directly, without the HTTP shim.
+//!
+//! The server is deliberately small: it frames JSON-RPC messages by hand and
+//! only implements the handful of methods needed to surface diagnostics and
+//! quick-fixes — `initialize`, `textDocument/didOpen`, `textDocument/didChange`
+//! and `textDocument/codeAction`.
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, Read, Write},
+    sync::Arc,
+};
+
+use divvun_runtime::bundle::Bundle;
+use serde_json::{json, Value};
+
+use crate::{check_document, GramcheckErrResponse, Language};
+
+/// Run the LSP server on stdin/stdout until the client closes the stream.
+pub async fn run(bundle: Arc<Bundle>, lang: Language) -> anyhow::Result<()> {
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    let mut reader = stdin.lock();
+    let mut writer = stdout.lock();
+
+    let mut server = Server {
+        bundle,
+        lang,
+        locales: Vec::new(),
+        documents: HashMap::new(),
+    };
+
+    while let Some(msg) = read_message(&mut reader)? {
+        let Some(method) = msg.get("method").and_then(Value::as_str) else {
+            // A response to a server-initiated request; we issue none, so ignore.
+            continue;
+        };
+        let id = msg.get("id").cloned();
+        let params = msg.get("params").cloned().unwrap_or(Value::Null);
+
+        match method {
+            "initialize" => {
+                server.initialize(&params);
+                if let Some(id) = id {
+                    write_message(&mut writer, &json!({ "id": id, "result": init_result() }))?;
+                }
+            }
+            "initialized" | "$/setTrace" => {}
+            "textDocument/didOpen" => {
+                if let Some((uri, diagnostics)) = server.did_open(&params).await {
+                    write_message(&mut writer, &publish(&uri, diagnostics))?;
+                }
+            }
+            "textDocument/didChange" => {
+                if let Some((uri, diagnostics)) = server.did_change(&params).await {
+                    write_message(&mut writer, &publish(&uri, diagnostics))?;
+                }
+            }
+            "textDocument/codeAction" => {
+                if let Some(id) = id {
+                    let result = server.code_action(&params).await;
+                    write_message(&mut writer, &json!({ "id": id, "result": result }))?;
+                }
+            }
+            "shutdown" => {
+                if let Some(id) = id {
+                    write_message(&mut writer, &json!({ "id": id, "result": Value::Null }))?;
+                }
+            }
+            "exit" => break,
+            other => tracing::debug!("Ignoring unsupported LSP method: {}", other),
+        }
+    }
+
+    Ok(())
+}
+
+/// Advertise the capabilities we actually drive: full-document text sync (so
+/// `didOpen`/`didChange` carry the whole document) and code actions for the
+/// suggestion quick-fixes.
+fn init_result() -> Value {
+    json!({
+        "capabilities": {
+            "textDocumentSync": 1, // TextDocumentSyncKind::Full
+            "codeActionProvider": true,
+        },
+        "serverInfo": {
+            "name": "divvun-worker-grammar",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+    })
+}
+
+struct Server {
+    bundle: Arc<Bundle>,
+    lang: Language,
+    /// Locales negotiated from `initializationOptions`, mirroring the HTTP
+    /// `Accept-Language` path.
+    locales: Vec<String>,
+    /// Last-seen text for each open document, keyed by URI.
+    documents: HashMap<String, String>,
+}
+
+impl Server {
+    fn initialize(&mut self, params: &Value) {
+        // Negotiate locale from the client's `initializationOptions`, the same
+        // way the HTTP path reads `Accept-Language`.
+        let accept = params
+            .get("initializationOptions")
+            .and_then(|o| o.get("locale").or_else(|| o.get("acceptLanguage")))
+            .and_then(Value::as_str)
+            .map(|s| s.to_string());
+
+        self.locales = crate::negotiate_locales(accept.as_deref(), self.lang.0.as_deref());
+    }
+
+    async fn check(&self, text: &str) -> Vec<GramcheckErrResponse> {
+        // LSP positions are UTF-16 code-unit based, so request utf-16 offsets.
+        match check_document(&self.bundle, text, &self.locales, true, None, None).await {
+            Ok(errs) => errs,
+            Err(e) => {
+                tracing::error!("LSP check failed: {:?}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    async fn did_open(&mut self, params: &Value) -> Option<(String, Vec<Value>)> {
+        let doc = params.get("textDocument")?;
+        let uri = doc.get("uri")?.as_str()?.to_string();
+        let text = doc.get("text")?.as_str()?.to_string();
+        let errs = self.check(&text).await;
+        let diagnostics = errs.iter().map(|e| diagnostic(&text, e)).collect();
+        self.documents.insert(uri.clone(), text);
+        Some((uri, diagnostics))
+    }
+
+    async fn did_change(&mut self, params: &Value) -> Option<(String, Vec<Value>)> {
+        let uri = params.get("textDocument")?.get("uri")?.as_str()?.to_string();
+        // We advertise full-document sync, so the last change holds the whole text.
+        let text = params
+            .get("contentChanges")?
+            .as_array()?
+            .last()?
+            .get("text")?
+            .as_str()?
+            .to_string();
+        let errs = self.check(&text).await;
+        let diagnostics = errs.iter().map(|e| diagnostic(&text, e)).collect();
+        self.documents.insert(uri.clone(), text);
+        Some((uri, diagnostics))
+    }
+
+    async fn code_action(&self, params: &Value) -> Vec<Value> {
+        let Some(uri) = params
+            .get("textDocument")
+            .and_then(|d| d.get("uri"))
+            .and_then(Value::as_str)
+        else {
+            return Vec::new();
+        };
+        let Some(text) = self.documents.get(uri) else {
+            return Vec::new();
+        };
+
+        // The client hands us the diagnostics for the requested range in
+        // `context.diagnostics`; re-run the checker so we can attach the
+        // suggestions carried alongside each error.
+        let requested = params
+            .get("context")
+            .and_then(|c| c.get("diagnostics"))
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        let wanted: Vec<&str> = requested
+            .iter()
+            .filter_map(|d| d.get("code").and_then(Value::as_str))
+            .collect();
+
+        let errs = self.check(text).await;
+
+        let mut actions = Vec::new();
+        for err in &errs {
+            if !wanted.is_empty() && !wanted.contains(&err.error_code.as_str()) {
+                continue;
+            }
+            let range = range_of(text, err.start_index, err.end_index);
+            for suggestion in &err.suggestions {
+                actions.push(json!({
+                    "title": format!("Replace with \u{201c}{}\u{201d}", suggestion),
+                    "kind": "quickfix",
+                    "diagnostics": [diagnostic(text, err)],
+                    "edit": {
+                        "changes": {
+                            uri: [{
+                                "range": range,
+                                "newText": suggestion,
+                            }],
+                        },
+                    },
+                }));
+            }
+        }
+        actions
+    }
+}
+
+fn publish(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "method": "textDocument/publishDiagnostics",
+        "params": {
+            "uri": uri,
+            "diagnostics": diagnostics,
+        },
+    })
+}
+
+fn diagnostic(text: &str, err: &GramcheckErrResponse) -> Value {
+    json!({
+        "range": range_of(text, err.start_index, err.end_index),
+        "code": err.error_code,
+        "source": "divvun",
+        "message": if err.description.is_empty() {
+            err.title.clone()
+        } else {
+            format!("{}\n{}", err.title, err.description)
+        },
+        "severity": 2, // Warning
+    })
+}
+
+/// Turn a flat `beg`/`end` pair of UTF-16 offsets into an LSP `Range` by walking
+/// the document's UTF-16 code units.
+fn range_of(text: &str, beg: u32, end: u32) -> Value {
+    json!({
+        "start": position_of(text, beg),
+        "end": position_of(text, end),
+    })
+}
+
+/// Convert a flat UTF-16 offset into a zero-based `{ line, character }` position,
+/// counting UTF-16 code units per line as LSP requires.
+fn position_of(text: &str, offset: u32) -> Value {
+    let mut line: u32 = 0;
+    let mut character: u32 = 0;
+    let mut units: u32 = 0;
+
+    for ch in text.chars() {
+        if units >= offset {
+            break;
+        }
+        if ch == '\n' {
+            line += 1;
+            character = 0;
+        } else {
+            character += ch.len_utf16() as u32;
+        }
+        units += ch.len_utf16() as u32;
+    }
+
+    json!({ "line": line, "character": character })
+}
+
+/// Read one `Content-Length`-framed JSON-RPC message, or `None` at EOF.
+fn read_message(reader: &mut impl BufRead) -> anyhow::Result<Option<Value>> {
+    let mut content_length: Option<usize> = None;
+
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            content_length = Some(value.trim().parse()?);
+        }
+    }
+
+    let Some(len) = content_length else {
+        anyhow::bail!("LSP message missing Content-Length header");
+    };
+
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf)?;
+    Ok(Some(serde_json::from_slice(&buf)?))
+}
+
+/// Write one JSON-RPC message with the mandatory `jsonrpc` field and framing.
+fn write_message(writer: &mut impl Write, message: &Value) -> anyhow::Result<()> {
+    let mut message = message.clone();
+    message["jsonrpc"] = json!("2.0");
+    let body = serde_json::to_vec(&message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n", body.len())?;
+    writer.write_all(&body)?;
+    writer.flush()?;
+    Ok(())
+}
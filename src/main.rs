@@ -3,26 +3,41 @@ use clap::Parser;
 use divvun_runtime::{bundle::Bundle, modules::Input, util::parse_accept_language};
 use futures_util::StreamExt;
 use poem::{
-    get, handler,
-    http::StatusCode,
+    i18n::{I18NResources, Locale},
     listener::TcpListener,
     middleware::Cors,
     post,
-    web::{Data, Html, Json, Query},
-    EndpointExt, IntoResponse, Request, Route, Server,
+    web::Data,
+    EndpointExt, Route, Server,
+};
+use poem_openapi::{
+    param::{Header, Query},
+    payload::{Html, Json},
+    ApiResponse, Enum, Object, OpenApi, OpenApiService,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::{path::Path, sync::Arc};
 
-#[derive(serde::Deserialize)]
+mod i18n;
+mod lsp;
+mod registry;
+mod stream;
+
+use registry::BundleRegistry;
+
+#[derive(Object, Deserialize)]
 struct ProcessInput {
+    /// The text to check for grammar errors.
     text: String,
+    /// Error codes to suppress from the response.
     ignore: Option<Vec<String>>,
+    /// Deprecated alias for `ignore`; kept for backwards compatibility.
+    #[oai(deprecated)]
     ignore_tags: Option<Vec<String>>,
 }
 
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Object, Deserialize, Serialize, Clone)]
 pub struct GramcheckErrResponse {
     pub error_text: String,
     pub start_index: u32,
@@ -33,74 +48,167 @@ pub struct GramcheckErrResponse {
     pub title: String,
 }
 
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Object, Deserialize, Serialize, Clone)]
 pub struct GramcheckResponse {
     pub text: String,
     pub errs: Vec<GramcheckErrResponse>,
 }
 
-#[derive(Deserialize)]
-struct ProcessQuery {
-    encoding: Option<String>,
+/// Character offset encoding used for `start_index`/`end_index`.
+#[derive(Enum, Clone, Copy)]
+enum Encoding {
+    /// UTF-8 byte offsets.
+    #[oai(rename = "utf-8")]
+    Utf8,
+    /// UTF-16 code-unit offsets (the default).
+    #[oai(rename = "utf-16")]
+    Utf16,
 }
 
-#[handler]
-async fn preferences_get(
-    Data(bundle): Data<&Arc<Bundle>>,
-    Data(lang): Data<&Language>,
-    req: &Request,
-) -> impl IntoResponse {
-    // Extract and parse Accept-Language header for locale configuration
-    let mut locales = if let Some(accept_lang) = req.header("Accept-Language") {
-        parse_accept_language(accept_lang)
-            .into_iter()
-            .map(|(lang_id, _)| lang_id.to_string())
-            .collect::<Vec<String>>()
-    } else {
-        Vec::new()
-    };
+/// Response for the grammar-check endpoint.
+#[derive(ApiResponse)]
+enum ProcessResponse {
+    #[oai(status = 200)]
+    Ok(Json<GramcheckResponse>),
+    /// No configured bundle matched the requested language.
+    #[oai(status = 404)]
+    NotFound,
+    /// The grammar pipeline failed while processing the document.
+    #[oai(status = 500)]
+    InternalError,
+}
 
-    // Add default language as fallback if not already present
-    if let Language(Some(lang)) = lang {
-        if !locales.contains(&lang) {
-            locales.push(lang.to_string());
-        }
-    }
+/// Response for the error-preferences endpoint.
+#[derive(ApiResponse)]
+enum PreferencesResponse {
+    #[oai(status = 200)]
+    Ok(Json<serde_json::Value>),
+    #[oai(status = 404)]
+    NotFound,
+    #[oai(status = 500)]
+    InternalError,
+}
 
-    let Some(suggest) = bundle.command::<divvun_runtime::modules::divvun::Suggest>("suggest")
-    else {
-        tracing::error!("Suggest command not found in bundle");
-        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-    };
+/// Response for the health endpoint.
+#[derive(ApiResponse)]
+enum HealthResponse {
+    #[oai(status = 200)]
+    Ok,
+    #[oai(status = 503)]
+    Unavailable,
+}
 
-    let locales = locales.iter().map(|x| &**x).collect::<Vec<&str>>();
-    let prefs = suggest.error_preferences(&locales);
+struct Api;
 
-    Json(json!({
-        "error_tags": prefs,
-    }))
-    .into_response()
-}
+#[OpenApi]
+impl Api {
+    /// Interactive demo page.
+    #[oai(path = "/", method = "get")]
+    async fn index(&self, Data(registry): Data<&Arc<BundleRegistry>>) -> Html<String> {
+        let lang = registry.default_language();
+        Html(PAGE.replace("%LANG%", lang.as_deref().unwrap_or("unknown")))
+    }
 
-async fn process(
-    Data(bundle): Data<&Arc<Bundle>>,
-    Data(lang): Data<&Language>,
-    Json(body): Json<ProcessInput>,
-    Query(query): Query<ProcessQuery>,
-    req: &Request,
-) -> impl IntoResponse {
-    let text = body.text.trim();
-    let is_utf16 = match query.encoding.as_deref() {
-        Some("utf-16") | None => true,
-        Some("utf-8") => false,
-        Some(enc) => {
-            tracing::error!("Unsupported encoding: {}", enc);
-            return StatusCode::BAD_REQUEST.into_response();
+    /// Check a document for grammar errors.
+    #[oai(path = "/", method = "post")]
+    async fn process(
+        &self,
+        Data(registry): Data<&Arc<BundleRegistry>>,
+        locale: Locale,
+        body: Json<ProcessInput>,
+        Query(encoding): Query<Option<Encoding>>,
+        Query(lang): Query<Option<String>>,
+        #[oai(name = "Accept-Language")] Header(accept_language): Header<Option<String>>,
+    ) -> ProcessResponse {
+        let body = body.0;
+        let text = body.text.trim().to_string();
+        let is_utf16 = match encoding {
+            Some(Encoding::Utf16) | None => true,
+            Some(Encoding::Utf8) => false,
+        };
+
+        let Some(bundle) = registry.resolve(lang.as_deref(), accept_language.as_deref()) else {
+            tracing::error!("No bundle matched language selection");
+            return ProcessResponse::NotFound;
+        };
+
+        let locales = negotiate_locales(
+            accept_language.as_deref(),
+            registry.default_language().as_deref(),
+        );
+
+        // Prefer 'ignore' over the deprecated 'ignore_tags'.
+        let ignore = body.ignore.as_ref().or(body.ignore_tags.as_ref());
+
+        // The negotiated `Locale` drives localized error titles/descriptions.
+        match check_document(&bundle, &text, &locales, is_utf16, ignore, Some(&locale)).await {
+            Ok(errs) => ProcessResponse::Ok(Json(GramcheckResponse { text, errs })),
+            Err(e) => {
+                tracing::error!("{:?}", e);
+                ProcessResponse::InternalError
+            }
         }
-    };
+    }
+
+    /// List the configurable error-tag preferences for a language.
+    #[oai(path = "/preferences", method = "get")]
+    async fn preferences(
+        &self,
+        Data(registry): Data<&Arc<BundleRegistry>>,
+        Query(lang): Query<Option<String>>,
+        #[oai(name = "Accept-Language")] Header(accept_language): Header<Option<String>>,
+    ) -> PreferencesResponse {
+        let Some(bundle) = registry.resolve(lang.as_deref(), accept_language.as_deref()) else {
+            return PreferencesResponse::NotFound;
+        };
+
+        let locales = negotiate_locales(
+            accept_language.as_deref(),
+            registry.default_language().as_deref(),
+        );
+
+        let Some(suggest) = bundle.command::<divvun_runtime::modules::divvun::Suggest>("suggest")
+        else {
+            tracing::error!("Suggest command not found in bundle");
+            return PreferencesResponse::InternalError;
+        };
+
+        let locales = locales.iter().map(|x| &**x).collect::<Vec<&str>>();
+        let prefs = suggest.error_preferences(&locales);
+
+        PreferencesResponse::Ok(Json(json!({ "error_tags": prefs })))
+    }
+
+    /// Liveness probe that drives the pipeline over an empty document.
+    #[oai(path = "/health", method = "get")]
+    async fn health(
+        &self,
+        Data(registry): Data<&Arc<BundleRegistry>>,
+        Query(lang): Query<Option<String>>,
+        #[oai(name = "Accept-Language")] Header(accept_language): Header<Option<String>>,
+    ) -> HealthResponse {
+        let Some(bundle) = registry.resolve(lang.as_deref(), accept_language.as_deref()) else {
+            return HealthResponse::Unavailable;
+        };
+        let locales = negotiate_locales(
+            accept_language.as_deref(),
+            registry.default_language().as_deref(),
+        );
+        match check_document(&bundle, "", &locales, true, None, None).await {
+            Ok(_) => HealthResponse::Ok,
+            Err(e) => {
+                tracing::error!("{:?}", e);
+                HealthResponse::Unavailable
+            }
+        }
+    }
+}
 
-    // Extract and parse Accept-Language header for locale configuration
-    let mut locales = if let Some(accept_lang) = req.header("Accept-Language") {
+/// Resolve the effective locale list from an `Accept-Language` header plus the
+/// server's default language, preserving client order and appending the
+/// fallback last.
+fn negotiate_locales(accept_lang: Option<&str>, default_lang: Option<&str>) -> Vec<String> {
+    let mut locales = if let Some(accept_lang) = accept_lang {
         parse_accept_language(accept_lang)
             .into_iter()
             .map(|(lang_id, _)| lang_id.to_string())
@@ -110,107 +218,111 @@ async fn process(
     };
 
     // Add default language as fallback if not already present
-    if let Language(Some(lang)) = lang {
-        if !locales.contains(lang) {
+    if let Some(lang) = default_lang {
+        if !locales.iter().any(|l| l == lang) {
             locales.push(lang.to_string());
         }
     }
 
-    // Build configuration with locales for suggestions
+    locales
+}
+
+/// Build the `suggest` pipeline configuration for a request.
+pub(crate) fn suggest_config(
+    locales: &[String],
+    is_utf16: bool,
+    ignore: Option<&Vec<String>>,
+) -> serde_json::Value {
     let mut suggest_config = serde_json::json!({
         "locales": locales,
         "encoding": if is_utf16 { "utf-16" } else { "utf-8" },
     });
 
-    // Handle ignore list - prefer 'ignore' over deprecated 'ignore_tags'
-    let ignore_list = body.ignore.as_ref().or(body.ignore_tags.as_ref());
-    if let Some(ignore_list) = ignore_list {
-        if !ignore_list.is_empty() {
-            suggest_config["ignore"] = serde_json::json!(ignore_list);
+    if let Some(ignore) = ignore {
+        if !ignore.is_empty() {
+            suggest_config["ignore"] = serde_json::json!(ignore);
         }
     }
 
-    let config = serde_json::json!({
-        "suggest": suggest_config
-    });
+    serde_json::json!({ "suggest": suggest_config })
+}
 
-    let mut pipeline = match bundle.create(config).await {
-        Ok(pipeline) => pipeline,
-        Err(e) => {
-            tracing::error!("Failed to create pipeline: {:?}", e);
-            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-        }
+/// Decode a single pipeline error object into a [`GramcheckErrResponse`],
+/// applying localized presentation when a messages bundle is available.
+pub(crate) fn decode_error(
+    obj: &serde_json::Value,
+    localizer: Option<&Locale>,
+) -> Option<GramcheckErrResponse> {
+    let form = obj.get("form")?.as_str()?.to_string();
+    let beg = obj.get("beg")?.as_u64()? as u32;
+    let end = obj.get("end")?.as_u64()? as u32;
+    let err = obj.get("err")?.as_str()?.to_string();
+    let msg = obj.get("msg")?.as_array()?;
+    let rep = obj
+        .get("rep")?
+        .as_array()?
+        .iter()
+        .filter_map(|s| s.as_str().map(|s| s.to_string()))
+        .collect();
+
+    let mut err = GramcheckErrResponse {
+        error_text: form,
+        start_index: beg,
+        end_index: end,
+        error_code: err,
+        title: msg.get(0)?.as_str()?.to_string(),
+        description: msg
+            .get(1)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string(),
+        suggestions: rep,
     };
 
+    if let Some(locale) = localizer {
+        i18n::localize(locale, &mut err);
+    }
+
+    Some(err)
+}
+
+/// Run `text` through the bundle pipeline and collect the grammar errors.
+///
+/// This is the shared core behind the HTTP `process` operation and the LSP
+/// server; the `/stream` endpoint drives the same pipeline incrementally.
+pub(crate) async fn check_document(
+    bundle: &Bundle,
+    text: &str,
+    locales: &[String],
+    is_utf16: bool,
+    ignore: Option<&Vec<String>>,
+    localizer: Option<&Locale>,
+) -> anyhow::Result<Vec<GramcheckErrResponse>> {
+    let config = suggest_config(locales, is_utf16, ignore);
+
+    let mut pipeline = bundle
+        .create(config)
+        .await
+        .context("Failed to create pipeline")?;
+
     let mut stream = pipeline.forward(Input::String(text.to_string())).await;
 
     let output = match stream.next().await {
-        Some(output) => match output {
-            Ok(output) => output,
-            Err(e) => {
-                tracing::error!("Failed to process text: {:?}", e);
-                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-            }
-        },
-        None => {
-            tracing::error!("No output from pipeline");
-            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-        }
+        Some(output) => output.context("Failed to process text")?,
+        None => anyhow::bail!("No output from pipeline"),
     };
 
     let result_json = match output {
-        Input::Json(s) => match s {
-            serde_json::Value::Array(x) => x,
-            _ => {
-                tracing::error!("Expected JSON array from pipeline");
-                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-            }
-        },
-        x => {
-            tracing::error!("{:?}", x);
-            tracing::error!("Unexpected output type from pipeline");
-            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-        }
+        Input::Json(serde_json::Value::Array(x)) => x,
+        x => anyhow::bail!("Unexpected output type from pipeline: {:?}", x),
     };
 
     tracing::debug!("Pipeline output: {:?}", result_json);
 
-    let result = result_json
+    Ok(result_json
         .iter()
-        .filter_map(|obj| {
-            let form = obj.get("form")?.as_str()?.to_string();
-            let beg = obj.get("beg")?.as_u64()? as u32;
-            let end = obj.get("end")?.as_u64()? as u32;
-            let err = obj.get("err")?.as_str()?.to_string();
-            let msg = obj.get("msg")?.as_array()?;
-            let rep = obj
-                .get("rep")?
-                .as_array()?
-                .iter()
-                .filter_map(|s| s.as_str().map(|s| s.to_string()))
-                .collect();
-
-            Some(GramcheckErrResponse {
-                error_text: form,
-                start_index: beg,
-                end_index: end,
-                error_code: err,
-                title: msg.get(0)?.as_str()?.to_string(),
-                description: msg
-                    .get(1)
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("")
-                    .to_string(),
-                suggestions: rep,
-            })
-        })
-        .collect::<Vec<_>>();
-
-    Json(GramcheckResponse {
-        text: text.to_string(),
-        errs: result,
-    })
-    .into_response()
+        .filter_map(|obj| decode_error(obj, localizer))
+        .collect())
 }
 
 const PAGE: &str = include_str!("../index.html");
@@ -218,54 +330,15 @@ const PAGE: &str = include_str!("../index.html");
 #[derive(Debug, Clone)]
 struct Language(Option<String>);
 
-#[handler]
-async fn process_get(Data(lang): Data<&Language>) -> impl IntoResponse {
-    Html(PAGE.replace("%LANG%", &lang.0.as_deref().unwrap_or("unknown"))).into_response()
-}
-
-#[handler]
-async fn process_post(
-    bundle: Data<&Arc<Bundle>>,
-    lang: Data<&Language>,
-    body: Json<ProcessInput>,
-    query: Query<ProcessQuery>,
-    req: &Request,
-) -> impl IntoResponse {
-    process(bundle, lang, body, query, req).await
-}
-
-#[handler]
-async fn health_check(req: &Request) -> impl IntoResponse {
-    let Some(bundle) = req.data::<Arc<Bundle>>() else {
-        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-    };
-
-    let Some(lang) = req.data::<Language>() else {
-        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-    };
-
-    let body = ProcessInput {
-        text: "".to_string(),
-        ignore: None,
-        ignore_tags: None,
-    };
-
-    let query = ProcessQuery { encoding: None };
-
-    let res = process(Data(bundle), Data(lang), Json(body), Query(query), req)
-        .await
-        .into_response();
-    res.status().into_response()
-}
-
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
-    /// Path to the grammar bundle file (.drb)
+    /// Grammar bundle source: a single `.drb` file, a directory of `.drb`
+    /// files, or a `.toml`/`.json` manifest listing `{ language, path }` entries
     #[arg(required = true)]
     bundle_path: String,
 
-    /// Default language for localizations (overrides bundle filename)
+    /// Default language id, selecting the bundle used when a request names none
     #[arg(long, env = "DEFAULT_LANGUAGE")]
     language: Option<String>,
 
@@ -276,6 +349,16 @@ struct Cli {
     /// Port to run the server on
     #[arg(long, env = "PORT", default_value_t = 4000)]
     port: u16,
+
+    /// Directory of Fluent `.ftl` message files used to localize error
+    /// titles and descriptions (defaults to a `messages` directory alongside
+    /// the bundle)
+    #[arg(long, env = "MESSAGES_DIR")]
+    messages_dir: Option<String>,
+
+    /// Speak the Language Server Protocol over stdio instead of serving HTTP
+    #[arg(long)]
+    lsp: bool,
 }
 
 #[tokio::main]
@@ -287,23 +370,61 @@ async fn main() -> anyhow::Result<()> {
 async fn run(cli: Cli) -> anyhow::Result<()> {
     tracing_subscriber::fmt::init();
 
-    let path = Path::new(&cli.bundle_path)
-        .canonicalize()
-        .context("Failed to canonicalize bundle path")?;
+    let source = Path::new(&cli.bundle_path);
+    tracing::info!("Loading grammar bundles from: {}", source.display());
 
-    tracing::info!("Loading grammar bundle from: {}", path.display());
+    let registry = BundleRegistry::load(source, cli.language.clone())
+        .context("Failed to load grammar bundles")?;
+    tracing::info!("Serving languages: {:?}", registry.languages());
+
+    if cli.lsp {
+        tracing::info!("Starting LSP server on stdio");
+        let Some(bundle) = registry.resolve(cli.language.as_deref(), None) else {
+            anyhow::bail!("No bundle available for LSP mode");
+        };
+        return lsp::run(bundle, Language(registry.default_language())).await;
+    }
+
+    // Reload bundles in place when their `.drb` files change on disk.
+    registry
+        .watch()
+        .context("Failed to start bundle file watcher")?;
+
+    // Discover localization messages either from `--messages-dir` or next to
+    // the bundle source; an empty resource set leaves the pipeline strings
+    // untouched.
+    let messages_dir = cli
+        .messages_dir
+        .map(std::path::PathBuf::from)
+        .or_else(|| {
+            let base = if source.is_dir() {
+                Some(source.to_path_buf())
+            } else {
+                source.parent().map(Path::to_path_buf)
+            };
+            base.map(|b| b.join("messages"))
+        })
+        .filter(|p| p.is_dir());
+    let resources = match messages_dir {
+        Some(dir) => {
+            tracing::info!("Loading localization messages from: {}", dir.display());
+            i18n::load_resources(&dir).context("Failed to load localization messages")?
+        }
+        None => I18NResources::builder().build()?,
+    };
 
-    let bundle = Arc::new(
-        Bundle::from_bundle(&path)
-            .context("Failed to load grammar bundle - ensure the .drb file is valid")?,
-    );
+    let api_service =
+        OpenApiService::new(Api, "divvun-worker-grammar", env!("CARGO_PKG_VERSION"));
+    let swagger_ui = api_service.swagger_ui();
+    let spec = api_service.spec_endpoint();
 
     let app = Route::new()
-        .at("/", post(process_post).get(process_get))
-        .at("/preferences", get(preferences_get))
-        .at("/health", get(health_check))
-        .data(bundle)
-        .data(Language(cli.language))
+        .nest("/", api_service)
+        .nest("/docs", swagger_ui)
+        .at("/openapi.json", spec)
+        .at("/stream", post(stream::stream_post))
+        .data(registry)
+        .data(resources)
         .with(Cors::default());
 
     Server::new(TcpListener::bind((cli.host, cli.port)))
@@ -0,0 +1,260 @@
+//! A registry of grammar bundles keyed by language id.
+//!
+//! Generalizes the former single `Arc<Bundle>` so one process can serve many
+//! languages. Bundles are sourced from either a directory of `.drb` files (the
+//! file stem becomes the language id) or a TOML/JSON manifest modeled on
+//! Helix's grammar configuration — a list of `{ language, path }` entries.
+//!
+//! The live map is held behind an [`ArcSwap`] so requests read it lock-free
+//! while a filesystem watcher swaps in reloaded bundles in the background.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use anyhow::Context;
+use arc_swap::ArcSwap;
+use divvun_runtime::{bundle::Bundle, util::parse_accept_language};
+use serde::Deserialize;
+
+/// A single `{ language, path }` entry in a manifest.
+#[derive(Debug, Deserialize)]
+struct LanguageEntry {
+    language: String,
+    path: PathBuf,
+}
+
+/// A TOML/JSON manifest listing the bundles to serve.
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    /// Language id to fall back to when a request names none.
+    #[serde(default)]
+    default: Option<String>,
+    languages: Vec<LanguageEntry>,
+}
+
+/// A loaded bundle together with the on-disk path it came from, so the watcher
+/// can reload it in place.
+struct Entry {
+    path: PathBuf,
+    bundle: Arc<Bundle>,
+}
+
+pub struct BundleRegistry {
+    bundles: ArcSwap<HashMap<String, Entry>>,
+    default_lang: Option<String>,
+}
+
+impl BundleRegistry {
+    /// Load a registry from `source`, which may be a directory of `.drb` files,
+    /// a `.toml`/`.json` manifest, or a single `.drb` file (in which case
+    /// `default_lang` or the file stem names the language).
+    pub fn load(source: &Path, default_lang: Option<String>) -> anyhow::Result<Arc<Self>> {
+        let (entries, manifest_default) = if source.is_dir() {
+            (scan_directory(source)?, None)
+        } else {
+            match source.extension().and_then(|e| e.to_str()) {
+                Some("toml") | Some("json") => load_manifest(source)?,
+                _ => {
+                    let language = default_lang
+                        .clone()
+                        .or_else(|| stem(source))
+                        .context("Could not determine language id for bundle")?;
+                    (vec![(language, source.to_path_buf())], None)
+                }
+            }
+        };
+
+        if entries.is_empty() {
+            anyhow::bail!("No grammar bundles found at {}", source.display());
+        }
+
+        let first_lang = entries.first().map(|(language, _)| language.clone());
+        let mut map = HashMap::new();
+        for (language, path) in entries {
+            tracing::info!("Loading bundle for {} from {}", language, path.display());
+            let bundle = load_bundle(&path)?;
+            map.insert(language, Entry { path, bundle });
+        }
+
+        // Explicit CLI default wins, then the manifest default, then the first
+        // configured language so there is always something to fall back to.
+        let default_lang = default_lang
+            .or(manifest_default)
+            .or(first_lang);
+
+        Ok(Arc::new(Self {
+            bundles: ArcSwap::from_pointee(map),
+            default_lang,
+        }))
+    }
+
+    /// Resolve the bundle for a request by (1) an explicit `lang`, (2) the
+    /// negotiated `Accept-Language` header, then (3) the configured default.
+    pub fn resolve(&self, explicit: Option<&str>, accept_lang: Option<&str>) -> Option<Arc<Bundle>> {
+        let map = self.bundles.load();
+
+        if let Some(lang) = explicit {
+            return map.get(lang).map(|e| e.bundle.clone());
+        }
+
+        if let Some(accept) = accept_lang {
+            for (lang_id, _) in parse_accept_language(accept) {
+                if let Some(entry) = map.get(&lang_id.to_string()) {
+                    return Some(entry.bundle.clone());
+                }
+            }
+        }
+
+        self.default_lang
+            .as_deref()
+            .and_then(|lang| map.get(lang))
+            .map(|e| e.bundle.clone())
+    }
+
+    /// The configured default language id, used for the landing page and LSP.
+    pub fn default_language(&self) -> Option<String> {
+        self.default_lang.clone()
+    }
+
+    /// The language ids currently served.
+    pub fn languages(&self) -> Vec<String> {
+        self.bundles.load().keys().cloned().collect()
+    }
+
+    /// Reload the bundle registered for `language` from disk and swap it in.
+    fn reload(&self, language: &str) -> anyhow::Result<()> {
+        let current = self.bundles.load();
+        let Some(entry) = current.get(language) else {
+            return Ok(());
+        };
+        let path = entry.path.clone();
+        let bundle = load_bundle(&path)?;
+
+        let mut next = HashMap::with_capacity(current.len());
+        for (lang, entry) in current.iter() {
+            next.insert(
+                lang.clone(),
+                Entry {
+                    path: entry.path.clone(),
+                    bundle: entry.bundle.clone(),
+                },
+            );
+        }
+        next.insert(language.to_string(), Entry { path, bundle });
+        self.bundles.store(Arc::new(next));
+        tracing::info!("Reloaded bundle for {}", language);
+        Ok(())
+    }
+
+    /// Watch every bundle file and reload it when its mtime changes so a
+    /// long-running server picks up updated models without a restart.
+    pub fn watch(self: &Arc<Self>) -> anyhow::Result<()> {
+        use notify::{RecursiveMode, Watcher};
+
+        // bundle path -> language id
+        let mut watched: HashMap<PathBuf, String> = HashMap::new();
+        for (lang, entry) in self.bundles.load().iter() {
+            watched.insert(entry.path.clone(), lang.clone());
+        }
+
+        let registry = Arc::clone(self);
+        let mut mtimes: HashMap<PathBuf, SystemTime> = HashMap::new();
+        for path in watched.keys() {
+            if let Ok(mtime) = mtime_of(path) {
+                mtimes.insert(path.clone(), mtime);
+            }
+        }
+
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            let Ok(event) = res else { return };
+            for path in event.paths {
+                let Some(language) = watched.get(&path) else {
+                    continue;
+                };
+                // Only reload when the mtime actually changed, collapsing the
+                // burst of events editors emit for a single save.
+                let changed = mtime_of(&path)
+                    .ok()
+                    .map(|m| mtimes.get(&path) != Some(&m))
+                    .unwrap_or(false);
+                if !changed {
+                    continue;
+                }
+                if let Ok(m) = mtime_of(&path) {
+                    mtimes.insert(path.clone(), m);
+                }
+                if let Err(e) = registry.reload(language) {
+                    tracing::error!("Failed to reload bundle for {}: {:?}", language, e);
+                }
+            }
+        })?;
+
+        for path in self.bundles.load().values().map(|e| e.path.clone()) {
+            watcher.watch(&path, RecursiveMode::NonRecursive)?;
+        }
+
+        // The watcher stops firing once dropped; leak it for the process lifetime.
+        std::mem::forget(watcher);
+        Ok(())
+    }
+}
+
+fn load_bundle(path: &Path) -> anyhow::Result<Arc<Bundle>> {
+    let path = path
+        .canonicalize()
+        .with_context(|| format!("Failed to canonicalize {}", path.display()))?;
+    Ok(Arc::new(Bundle::from_bundle(&path).with_context(|| {
+        format!("Failed to load grammar bundle {}", path.display())
+    })?))
+}
+
+fn scan_directory(dir: &Path) -> anyhow::Result<Vec<(String, PathBuf)>> {
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) == Some("drb") {
+            if let Some(language) = stem(&path) {
+                entries.push((language, path));
+            }
+        }
+    }
+    Ok(entries)
+}
+
+fn load_manifest(path: &Path) -> anyhow::Result<(Vec<(String, PathBuf)>, Option<String>)> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read manifest {}", path.display()))?;
+    let manifest: Manifest = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => serde_json::from_str(&text)?,
+        _ => toml::from_str(&text)?,
+    };
+
+    // Resolve relative bundle paths against the manifest's directory.
+    let base = path.parent().unwrap_or_else(|| Path::new("."));
+    let entries = manifest
+        .languages
+        .into_iter()
+        .map(|e| {
+            let path = if e.path.is_absolute() {
+                e.path
+            } else {
+                base.join(e.path)
+            };
+            (e.language, path)
+        })
+        .collect();
+
+    Ok((entries, manifest.default))
+}
+
+fn stem(path: &Path) -> Option<String> {
+    path.file_stem().and_then(|s| s.to_str()).map(String::from)
+}
+
+fn mtime_of(path: &Path) -> std::io::Result<SystemTime> {
+    std::fs::metadata(path)?.modified()
+}
@@ -0,0 +1,117 @@
+//! Incremental grammar-checking over Server-Sent Events.
+//!
+//! Where the `process` operation buffers the whole `GramcheckResponse`, this
+//! endpoint drives the pipeline over a (potentially large) document and flushes
+//! each `GramcheckErrResponse` to the client as soon as the pipeline yields it.
+//! A final `done` event carries aggregate metadata. Interactive clients can
+//! underline early paragraphs while later ones are still being analyzed.
+
+use std::sync::Arc;
+
+use divvun_runtime::modules::Input;
+use futures_util::StreamExt;
+use poem::{
+    handler,
+    http::StatusCode,
+    i18n::Locale,
+    web::{
+        sse::{Event, SSE},
+        Data, Json, Query,
+    },
+    IntoResponse, Request, Response,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{decode_error, negotiate_locales, suggest_config, BundleRegistry, ProcessInput};
+
+#[derive(Deserialize)]
+pub struct StreamQuery {
+    encoding: Option<String>,
+    lang: Option<String>,
+}
+
+/// `POST /stream` — emit grammar errors incrementally as SSE events.
+#[handler]
+pub async fn stream_post(
+    Data(registry): Data<&Arc<BundleRegistry>>,
+    locale: Locale,
+    Json(body): Json<ProcessInput>,
+    Query(query): Query<StreamQuery>,
+    req: &Request,
+) -> Response {
+    let is_utf16 = match query.encoding.as_deref() {
+        Some("utf-16") | None => true,
+        Some("utf-8") => false,
+        Some(enc) => {
+            tracing::error!("Unsupported encoding: {}", enc);
+            return StatusCode::BAD_REQUEST.into_response();
+        }
+    };
+
+    let accept_language = req.header("Accept-Language").map(str::to_string);
+    let Some(bundle) = registry.resolve(query.lang.as_deref(), accept_language.as_deref()) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let locales = negotiate_locales(
+        accept_language.as_deref(),
+        registry.default_language().as_deref(),
+    );
+    let ignore = body.ignore.clone().or(body.ignore_tags.clone());
+    let config = suggest_config(&locales, is_utf16, ignore.as_ref());
+    let text = body.text.trim().to_string();
+
+    // Drive the pipeline in a background task and forward each decoded error
+    // through a channel; the SSE body reads the channel until the task closes
+    // it after emitting the aggregate `done` event.
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<Event>(16);
+
+    tokio::spawn(async move {
+        let mut pipeline = match bundle.create(config).await {
+            Ok(pipeline) => pipeline,
+            Err(e) => {
+                tracing::error!("Failed to create pipeline: {:?}", e);
+                let _ = tx.send(error_event(&e.to_string())).await;
+                return;
+            }
+        };
+
+        let mut stream = pipeline.forward(Input::String(text)).await;
+        let mut total = 0usize;
+
+        while let Some(item) = stream.next().await {
+            let arr = match item {
+                Ok(Input::Json(serde_json::Value::Array(arr))) => arr,
+                Ok(_) => continue,
+                Err(e) => {
+                    tracing::error!("Failed to process text: {:?}", e);
+                    let _ = tx.send(error_event(&e.to_string())).await;
+                    continue;
+                }
+            };
+
+            for obj in &arr {
+                if let Some(err) = decode_error(obj, Some(&locale)) {
+                    total += 1;
+                    let payload = serde_json::to_string(&err).unwrap_or_default();
+                    if tx.send(Event::message(payload).event_type("error")).await.is_err() {
+                        return; // client disconnected
+                    }
+                }
+            }
+        }
+
+        let done = json!({ "total_errors": total });
+        let _ = tx
+            .send(Event::message(done.to_string()).event_type("done"))
+            .await;
+    });
+
+    let body = futures_util::stream::poll_fn(move |cx| rx.poll_recv(cx));
+    SSE::new(body).into_response()
+}
+
+fn error_event(message: &str) -> Event {
+    Event::message(json!({ "error": message }).to_string()).event_type("error")
+}
@@ -0,0 +1,253 @@
+//! End-to-end HTTP tests that boot the real server binary against a fixture
+//! bundle.
+//!
+//! The binary is compiled with `escargot` (as in the `ax` smoke tests) and
+//! launched on an ephemeral port against a fixture `.drb`. Once
+//! `/health` reports `200` the tests assert the JSON shape of the public
+//! endpoints, covering the `utf-8`/`utf-16` index arithmetic, the
+//! `ignore`/`ignore_tags` handling and `Accept-Language` negotiation.
+//!
+//! Because a real grammar bundle is a large binary artifact, the fixture path
+//! is taken from `$GRAMMAR_FIXTURE` or the first `*.drb` under
+//! `tests/fixtures/`. The suite fails loudly when no fixture is available so a
+//! misconfigured CI run cannot pass vacuously — point `GRAMMAR_FIXTURE` at a
+//! bundle or commit one under `tests/fixtures/`.
+
+use std::{
+    io::{BufRead, BufReader, Read},
+    net::TcpListener,
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// A running server child whose stdout/stderr is drained into a shared buffer
+/// so failures can surface the server logs.
+struct Server {
+    child: Child,
+    port: u16,
+    logs: Arc<Mutex<String>>,
+}
+
+impl Server {
+    fn url(&self, path: &str) -> String {
+        format!("http://127.0.0.1:{}{}", self.port, path)
+    }
+
+    fn logs(&self) -> String {
+        self.logs.lock().unwrap().clone()
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Locate a fixture bundle, or `None` when none is available (the suite then
+/// fails).
+fn fixture() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("GRAMMAR_FIXTURE") {
+        return Some(PathBuf::from(path));
+    }
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    std::fs::read_dir(dir).ok()?.filter_map(Result::ok).find_map(|e| {
+        let path = e.path();
+        (path.extension().and_then(|x| x.to_str()) == Some("drb")).then_some(path)
+    })
+}
+
+/// Grab a free TCP port by binding to `:0` and releasing it immediately.
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+fn boot(fixture: &PathBuf) -> Server {
+    let bin = escargot::CargoBuild::new()
+        .run()
+        .expect("failed to build binary");
+
+    let port = free_port();
+    let mut child = bin
+        .command()
+        .arg(fixture)
+        .arg("--host")
+        .arg("127.0.0.1")
+        .arg("--port")
+        .arg(port.to_string())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn server");
+
+    // Drain both pipes into a shared buffer for diagnostics.
+    let logs = Arc::new(Mutex::new(String::new()));
+    for pipe in [
+        Box::new(child.stdout.take().unwrap()) as Box<dyn Read + Send>,
+        Box::new(child.stderr.take().unwrap()) as Box<dyn Read + Send>,
+    ] {
+        let logs = Arc::clone(&logs);
+        thread::spawn(move || {
+            let mut reader = BufReader::new(pipe);
+            let mut line = String::new();
+            while reader.read_line(&mut line).map(|n| n > 0).unwrap_or(false) {
+                logs.lock().unwrap().push_str(&line);
+                line.clear();
+            }
+        });
+    }
+
+    let server = Server { child, port, logs };
+    wait_for_health(&server);
+    server
+}
+
+/// Poll `/health` until it returns `200`, failing with captured logs on timeout.
+fn wait_for_health(server: &Server) {
+    let deadline = Instant::now() + Duration::from_secs(30);
+    let client = reqwest::blocking::Client::new();
+    while Instant::now() < deadline {
+        if let Ok(resp) = client.get(server.url("/health")).send() {
+            if resp.status().is_success() {
+                return;
+            }
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+    panic!("server never became healthy:\n{}", server.logs());
+}
+
+/// POST a document (optionally with an `encoding` query) and decode the body.
+fn check(
+    client: &reqwest::blocking::Client,
+    server: &Server,
+    text: &str,
+    encoding: Option<&str>,
+) -> serde_json::Value {
+    let path = match encoding {
+        Some(enc) => format!("/?encoding={enc}"),
+        None => "/".to_string(),
+    };
+    client
+        .post(server.url(&path))
+        .json(&serde_json::json!({ "text": text }))
+        .send()
+        .unwrap()
+        .json()
+        .unwrap()
+}
+
+/// Extract the `start_index` of each error in a `GramcheckResponse` body.
+fn start_indices(resp: &serde_json::Value) -> Vec<u64> {
+    resp["errs"]
+        .as_array()
+        .map(|errs| errs.iter().filter_map(|e| e["start_index"].as_u64()).collect())
+        .unwrap_or_default()
+}
+
+#[test]
+fn http_surface() {
+    let fixture = fixture().expect(
+        "no fixture bundle available; check in a `tests/fixtures/*.drb` or run CI with \
+         GRAMMAR_FIXTURE pointing at one so this suite actually exercises the server",
+    );
+
+    let server = boot(&fixture);
+    let client = reqwest::blocking::Client::new();
+
+    // Landing page renders.
+    let index = client.get(server.url("/")).send().unwrap();
+    assert!(index.status().is_success(), "logs:\n{}", server.logs());
+
+    // POST `/` returns a well-formed response with the (trimmed) text echoed.
+    let resp: serde_json::Value = client
+        .post(server.url("/"))
+        .json(&serde_json::json!({ "text": "  some input text  " }))
+        .send()
+        .unwrap()
+        .json()
+        .unwrap();
+    assert_eq!(resp["text"], "some input text");
+    assert!(resp["errs"].is_array(), "errs must be an array");
+
+    // For pure-ASCII text the utf-8 and utf-16 offsets must agree.
+    let utf8 = check(&client, &server, "some input text", Some("utf-8"));
+    let utf16 = check(&client, &server, "some input text", Some("utf-16"));
+    assert_eq!(utf8["errs"], utf16["errs"], "ASCII offsets must match");
+
+    // For text with an astral character (4 UTF-8 bytes, 2 UTF-16 code units)
+    // ahead of the errors, the two encodings must report different offsets.
+    let sample = "𝓐 sååsnoskorrekt teksta";
+    let utf8 = check(&client, &server, sample, Some("utf-8"));
+    let utf16 = check(&client, &server, sample, Some("utf-16"));
+    let u8_idx = start_indices(&utf8);
+    let u16_idx = start_indices(&utf16);
+    assert_eq!(
+        u8_idx.len(),
+        u16_idx.len(),
+        "the same errors should surface under both encodings"
+    );
+    if u8_idx.is_empty() {
+        eprintln!("note: fixture produced no errors for non-ASCII sample; divergence unexercised");
+    } else {
+        assert_ne!(
+            u8_idx, u16_idx,
+            "UTF-8 and UTF-16 offsets must diverge past an astral character"
+        );
+        for (a, b) in u8_idx.iter().zip(&u16_idx) {
+            assert!(a >= b, "UTF-8 offset must be >= the UTF-16 offset");
+        }
+    }
+
+    // An unknown encoding is a client error.
+    let bad = client
+        .post(server.url("/?encoding=latin1"))
+        .json(&serde_json::json!({ "text": "x" }))
+        .send()
+        .unwrap();
+    assert_eq!(bad.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    // An unknown language has no bundle.
+    let missing = client
+        .post(server.url("/?lang=zz"))
+        .json(&serde_json::json!({ "text": "x" }))
+        .send()
+        .unwrap();
+    assert_eq!(missing.status(), reqwest::StatusCode::NOT_FOUND);
+
+    // `ignore` and its deprecated alias `ignore_tags` are both accepted.
+    for key in ["ignore", "ignore_tags"] {
+        let resp = client
+            .post(server.url("/"))
+            .json(&serde_json::json!({ "text": "x", key: ["typo"] }))
+            .send()
+            .unwrap();
+        assert!(resp.status().is_success(), "{key} rejected:\n{}", server.logs());
+    }
+
+    // `Accept-Language` negotiation is honored.
+    let localized = client
+        .post(server.url("/"))
+        .header("Accept-Language", "se, nb;q=0.8")
+        .json(&serde_json::json!({ "text": "x" }))
+        .send()
+        .unwrap();
+    assert!(localized.status().is_success());
+
+    // `/preferences` exposes the configurable error tags.
+    let prefs: serde_json::Value = client
+        .get(server.url("/preferences"))
+        .send()
+        .unwrap()
+        .json()
+        .unwrap();
+    assert!(prefs.get("error_tags").is_some(), "logs:\n{}", server.logs());
+}